@@ -0,0 +1,208 @@
+//! Local publish/subscribe bus that keeps multiple Aseprite instances in sync.
+//!
+//! A single broker owns a per-user Unix domain socket; every other instance
+//! connects to it as a client. Publishers send newline-delimited JSON
+//! [`Message`]s and the broker rebroadcasts each one to all *other* connected
+//! peers (never back to the sender). Received messages whose `kind` has been
+//! subscribed to are dropped into a receive queue the editor's main loop drains
+//! with [`poll`] each frame.
+//!
+//! The socket transport is Unix-only for now. A Windows named-pipe backend is
+//! not yet implemented: on non-Unix platforms the bus degrades to a local no-op
+//! (`sub`/`poll` keep working, `pub` simply has no peers to reach) so the crate
+//! still compiles and the editor still runs.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+/// A bus message: an opaque `payload` string tagged with a routing `kind`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Message {
+    pub kind: String,
+    pub payload: String,
+}
+
+/// Process-wide bus state, lazily initialized on first `pub`/`sub`/`poll`.
+struct Bus {
+    /// Kinds this instance wants delivered to its receive queue.
+    subscriptions: Mutex<HashSet<String>>,
+    /// Messages destined for this instance, drained by [`poll`].
+    incoming: Mutex<VecDeque<Message>>,
+    /// Connected peers to write published messages to (Unix transport only).
+    #[cfg(unix)]
+    peers: Mutex<Vec<transport::Peer>>,
+}
+
+static BUS: OnceLock<Bus> = OnceLock::new();
+
+/// Initializes the bus once: elects a broker (whoever binds the socket first)
+/// and otherwise joins as a client. Subsequent calls are cheap no-ops.
+fn bus() -> &'static Bus {
+    BUS.get_or_init(|| {
+        let bus = Bus {
+            subscriptions: Mutex::new(HashSet::new()),
+            incoming: Mutex::new(VecDeque::new()),
+            #[cfg(unix)]
+            peers: Mutex::new(Vec::new()),
+        };
+        transport::start(&bus);
+        bus
+    })
+}
+
+/// Enqueues `message` locally if its kind is currently subscribed.
+fn deliver(message: &Message) {
+    if bus().subscriptions.lock().unwrap().contains(&message.kind) {
+        bus().incoming.lock().unwrap().push_back(message.clone());
+    }
+}
+
+/// Publishes a message to all other connected instances.
+pub fn publish(kind: String, payload: String) {
+    let message = Message { kind, payload };
+    transport::broadcast(&message, None);
+}
+
+/// Subscribes this instance to `kind`; matching messages reach [`poll`].
+pub fn subscribe(kind: String) {
+    bus().subscriptions.lock().unwrap().insert(kind);
+}
+
+/// Non-blocking drain of the receive queue, oldest first.
+pub fn poll() -> Vec<Message> {
+    bus().incoming.lock().unwrap().drain(..).collect()
+}
+
+#[cfg(unix)]
+mod transport {
+    use super::{bus, deliver, Bus, Message};
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// A connected peer and the identity used to exclude it from its own echoes.
+    pub struct Peer {
+        id: u64,
+        stream: UnixStream,
+    }
+
+    static NEXT_PEER_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn next_peer_id() -> u64 {
+        NEXT_PEER_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Returns the per-user socket path, namespaced so distinct users don't clash.
+    fn socket_path() -> std::path::PathBuf {
+        let mut dir = std::env::temp_dir();
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "default".to_string());
+        dir.push(format!("aseprite-dmi-{user}.sock"));
+        dir
+    }
+
+    // `start` and `join_broker` take a non-static `&Bus` and thread it through,
+    // because they run synchronously inside `bus()`'s `get_or_init`: the `Bus`
+    // isn't published to the `OnceLock` yet, so calling `bus()` here would
+    // re-enter `get_or_init` on the same thread and deadlock.
+    pub fn start(bus: &Bus) {
+        let path = socket_path();
+        match UnixListener::bind(&path) {
+            Ok(listener) => run_broker(listener),
+            Err(_) => {
+                // Socket already exists: either a live broker or a stale file.
+                if let Ok(stream) = UnixStream::connect(&path) {
+                    join_broker(bus, stream);
+                } else {
+                    // Stale socket from a crashed broker: reclaim it.
+                    let _ = std::fs::remove_file(&path);
+                    if let Ok(listener) = UnixListener::bind(&path) {
+                        run_broker(listener);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Registers `stream` as a peer of `bus` and returns the id assigned to it.
+    fn register(bus: &Bus, stream: &UnixStream) -> Option<u64> {
+        let write_half = stream.try_clone().ok()?;
+        let id = next_peer_id();
+        bus.peers.lock().unwrap().push(Peer {
+            id,
+            stream: write_half,
+        });
+        Some(id)
+    }
+
+    /// Broker loop: accept clients, track their streams, and rebroadcast every
+    /// line received from one client to all the *others*.
+    fn run_broker(listener: UnixListener) {
+        std::thread::spawn(move || {
+            // This runs on its own thread after `get_or_init` has returned, so
+            // resolving the published `&'static Bus` via `bus()` is safe here.
+            let bus = bus();
+            for stream in listener.incoming().flatten() {
+                let peer_id = register(bus, &stream);
+                spawn_reader(stream, peer_id);
+            }
+        });
+    }
+
+    /// Client: remember the broker as our sole peer and read broadcasts from it.
+    fn join_broker(bus: &Bus, stream: UnixStream) {
+        register(bus, &stream);
+        // A client never rebroadcasts, so it has no originating peer to exclude.
+        spawn_reader(stream, None);
+    }
+
+    /// Reads newline-delimited JSON from `stream`. When acting as broker
+    /// (`from_id` is `Some`), fans each message out to every *other* peer;
+    /// either way, delivers matching messages to the local queue.
+    fn spawn_reader(stream: UnixStream, from_id: Option<u64>) {
+        std::thread::spawn(move || {
+            let reader = BufReader::new(stream);
+            for line in reader.lines().map_while(Result::ok) {
+                if let Ok(message) = serde_json::from_str::<Message>(&line) {
+                    if from_id.is_some() {
+                        broadcast(&message, from_id);
+                    }
+                    deliver(&message);
+                }
+            }
+        });
+    }
+
+    /// Writes `message` to every connected peer except `exclude`, dropping any
+    /// that have closed.
+    pub fn broadcast(message: &Message, exclude: Option<u64>) {
+        let Ok(mut line) = serde_json::to_string(message) else {
+            return;
+        };
+        line.push('\n');
+
+        let mut peers = bus().peers.lock().unwrap();
+        peers.retain_mut(|peer| {
+            if Some(peer.id) == exclude {
+                return true;
+            }
+            peer.stream.write_all(line.as_bytes()).is_ok()
+        });
+    }
+}
+
+#[cfg(not(unix))]
+mod transport {
+    use super::{Bus, Message};
+
+    pub fn start(_bus: &Bus) {
+        // No socket transport on non-Unix platforms yet (see module docs).
+    }
+
+    pub fn broadcast(_message: &Message, _exclude: Option<u64>) {
+        // No peers to reach without a transport; `pub` is a local no-op.
+    }
+}
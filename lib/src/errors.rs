@@ -0,0 +1,136 @@
+//! Layered error type surfaced to Lua.
+//!
+//! Failures carry a `source` chain and optional contextual segments so that a
+//! bug report shows *which* file, chunk, or stage went wrong instead of a bare
+//! message. Each operation annotates its errors with [`Context::context`]
+//! (e.g. `"reading Description chunk from <path>"`); the segments unwind into a single
+//! formatted chain when the error is converted to a [`mlua::Error`].
+
+use std::error::Error;
+use std::fmt;
+
+/// An error raised while servicing a request from the Aseprite side.
+#[derive(Debug)]
+pub enum ExternalError {
+    Io(std::io::Error),
+    Image(image::ImageError),
+    Serde(serde_json::Error),
+    Arboard(arboard::Error),
+    /// A bare message with no wrapped cause.
+    Message(String),
+    /// A contextual segment describing what the wrapped error happened during.
+    Context {
+        context: String,
+        source: Box<ExternalError>,
+    },
+}
+
+impl ExternalError {
+    /// Wraps this error with a contextual segment describing the failing stage.
+    pub fn context(self, segment: impl Into<String>) -> Self {
+        ExternalError::Context {
+            context: segment.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for ExternalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternalError::Io(e) => write!(f, "{e}"),
+            ExternalError::Image(e) => write!(f, "{e}"),
+            ExternalError::Serde(e) => write!(f, "{e}"),
+            ExternalError::Arboard(e) => write!(f, "{e}"),
+            ExternalError::Message(msg) => write!(f, "{msg}"),
+            // Unwind the chain: "reading Description chunk from a.dmi: decompressing DMI metadata: ..."
+            ExternalError::Context { context, source } => write!(f, "{context}: {source}"),
+        }
+    }
+}
+
+impl Error for ExternalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExternalError::Io(e) => Some(e),
+            ExternalError::Image(e) => Some(e),
+            ExternalError::Serde(e) => Some(e),
+            ExternalError::Arboard(e) => Some(e),
+            ExternalError::Message(_) => None,
+            ExternalError::Context { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+impl From<std::io::Error> for ExternalError {
+    fn from(e: std::io::Error) -> Self {
+        ExternalError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for ExternalError {
+    fn from(e: image::ImageError) -> Self {
+        ExternalError::Image(e)
+    }
+}
+
+impl From<serde_json::Error> for ExternalError {
+    fn from(e: serde_json::Error) -> Self {
+        ExternalError::Serde(e)
+    }
+}
+
+impl From<arboard::Error> for ExternalError {
+    fn from(e: arboard::Error) -> Self {
+        ExternalError::Arboard(e)
+    }
+}
+
+impl From<&str> for ExternalError {
+    fn from(msg: &str) -> Self {
+        ExternalError::Message(msg.to_string())
+    }
+}
+
+impl From<String> for ExternalError {
+    fn from(msg: String) -> Self {
+        ExternalError::Message(msg)
+    }
+}
+
+impl From<ExternalError> for mlua::Error {
+    fn from(err: ExternalError) -> Self {
+        // Flatten the whole context chain into one message for Lua.
+        mlua::Error::external(err.to_string())
+    }
+}
+
+/// Attaches contextual segments to a fallible result, converting its error into
+/// an [`ExternalError`] along the way.
+pub trait Context<T> {
+    /// Adds a fixed context segment.
+    fn context(self, segment: impl Into<String>) -> Result<T, ExternalError>;
+
+    /// Adds a context segment computed lazily (only on the error path).
+    fn with_context<F, S>(self, f: F) -> Result<T, ExternalError>
+    where
+        F: FnOnce() -> S,
+        S: Into<String>;
+}
+
+impl<T, E> Context<T> for Result<T, E>
+where
+    E: Into<ExternalError>,
+{
+    fn context(self, segment: impl Into<String>) -> Result<T, ExternalError> {
+        self.map_err(|e| e.into().context(segment))
+    }
+
+    fn with_context<F, S>(self, f: F) -> Result<T, ExternalError>
+    where
+        F: FnOnce() -> S,
+        S: Into<String>,
+    {
+        self.map_err(|e| e.into().context(f().into()))
+    }
+}
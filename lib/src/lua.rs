@@ -1,12 +1,19 @@
 use mlua::prelude::*;
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::ffi::OsStr;
 use std::fs::{self, read_dir, remove_dir_all};
 use std::path::Path;
+use std::rc::Rc;
 
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+
+use crate::bus;
 use crate::dmi::*;
-use crate::errors::ExternalError;
+use crate::errors::{Context, ExternalError};
 use crate::macros::safe;
+use crate::scratch::{self, FrameScratch};
 use crate::utils::check_latest_version;
 
 #[mlua::lua_module(name = "dmi_module")]
@@ -31,9 +38,170 @@ fn module(lua: &Lua) -> LuaResult<LuaTable> {
     exports.set("save_dialog", lua.create_function(safe!(save_dialog))?)?;
     exports.set("merge_spritesheet", lua.create_function(safe!(merge_spritesheet))?)?;
 
+    // Userdata handles: the primary API. Lua holds a live `Dmi` handle and
+    // mutates it in place instead of marshaling the whole sprite sheet across
+    // the FFI boundary on every edit. The free functions above remain as thin
+    // table-based wrappers for backward compatibility.
+    exports.set("new_dmi", lua.create_function(safe!(new_dmi))?)?;
+    exports.set("open_dmi", lua.create_function(safe!(open_dmi))?)?;
+
+    // Background, memory-bounded transforms driven through a scratch file.
+    exports.set("resize_async", lua.create_function(safe!(resize_async))?)?;
+    exports.set("crop_async", lua.create_function(safe!(crop_async))?)?;
+    exports.set("expand_async", lua.create_function(safe!(expand_async))?)?;
+
+    // Local pub/sub bus for keeping sibling Aseprite instances in sync.
+    exports.set("pub", lua.create_function(publish)?)?;
+    exports.set("sub", lua.create_function(subscribe)?)?;
+    exports.set("poll", lua.create_function(poll)?)?;
+
     Ok(exports)
 }
 
+/// Live, in-memory handle to a [`Dmi`] exposed to Lua as userdata.
+///
+/// Wrapping the `Dmi` in `Rc<RefCell<_>>` lets multiple Lua references share
+/// the same sprite sheet while methods borrow it mutably for the duration of a
+/// single call, so edits never round-trip through a Lua table.
+#[derive(Clone)]
+struct DmiHandle {
+    dmi: Rc<RefCell<Dmi>>,
+    temp: String,
+}
+
+/// Live handle to a single [`State`] owned by a [`DmiHandle`].
+#[derive(Clone)]
+struct StateHandle {
+    dmi: Rc<RefCell<Dmi>>,
+    index: usize,
+}
+
+fn new_dmi(
+    _: &Lua,
+    (name, width, height, temp): (String, u32, u32, String),
+) -> LuaResult<DmiHandle> {
+    Ok(DmiHandle {
+        dmi: Rc::new(RefCell::new(Dmi::new(name, width, height))),
+        temp,
+    })
+}
+
+fn open_dmi(_: &Lua, (filename, temp): (String, String)) -> LuaResult<DmiHandle> {
+    if !Path::new(&filename).is_file() {
+        Err("File does not exist".to_string()).into_lua_err()?
+    }
+
+    Ok(DmiHandle {
+        dmi: Rc::new(RefCell::new(
+            Dmi::open(&filename).with_context(|| format!("opening DMI from {filename}"))?,
+        )),
+        temp,
+    })
+}
+
+impl LuaUserData for DmiHandle {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("name", |_, this| Ok(this.dmi.borrow().name.clone()));
+        fields.add_field_method_get("width", |_, this| Ok(this.dmi.borrow().width));
+        fields.add_field_method_get("height", |_, this| Ok(this.dmi.borrow().height));
+        fields.add_field_method_get("temp", |_, this| Ok(this.temp.clone()));
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("resize", |_, this, (width, height, method): (u32, u32, String)| {
+            this.dmi
+                .borrow_mut()
+                .resize(width, height, filter_type(&method)?);
+            Ok(())
+        });
+
+        methods.add_method_mut("crop", |_, this, (x, y, width, height): (u32, u32, u32, u32)| {
+            this.dmi.borrow_mut().crop(x, y, width, height);
+            Ok(())
+        });
+
+        methods.add_method_mut("expand", |_, this, (x, y, width, height): (u32, u32, u32, u32)| {
+            this.dmi.borrow_mut().expand(x, y, width, height);
+            Ok(())
+        });
+
+        methods.add_method_mut("add_state", |_, this, (): ()| {
+            let (width, height) = {
+                let dmi = this.dmi.borrow();
+                (dmi.width, dmi.height)
+            };
+            let mut dmi = this.dmi.borrow_mut();
+            let index = dmi.states.len();
+            dmi.states
+                .push(State::new_blank(String::new(), width, height));
+            Ok(StateHandle {
+                dmi: this.dmi.clone(),
+                index,
+            })
+        });
+
+        methods.add_method("state", |_, this, index: usize| {
+            let len = this.dmi.borrow().states.len();
+            if index == 0 || index > len {
+                return Err(LuaError::external(format!(
+                    "state index {index} out of range (1..={len})"
+                )));
+            }
+            Ok(StateHandle {
+                dmi: this.dmi.clone(),
+                index: index - 1,
+            })
+        });
+
+        methods.add_method("state_count", |_, this, (): ()| Ok(this.dmi.borrow().states.len()));
+
+        methods.add_method("save", |_, this, path: String| {
+            // Save through a borrow — no full-sheet clone per save.
+            this.dmi
+                .borrow()
+                .save(path.clone())
+                .with_context(|| format!("saving DMI to {path}"))?;
+            bus::publish("dmi-changed".to_string(), path);
+            Ok(())
+        });
+
+        // Escape hatch: materialize the handle as the legacy serialized table
+        // so existing Lua call sites keep working during the migration.
+        methods.add_method("serialize", |lua, this, (): ()| {
+            let dmi = this.dmi.borrow().clone().to_serialized(this.temp.clone(), false)?;
+            dmi.into_lua_table(lua)
+        });
+    }
+}
+
+impl LuaUserData for StateHandle {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("index", |_, this| Ok(this.index + 1));
+        fields.add_field_method_get("name", |_, this| {
+            Ok(this.dmi.borrow().states[this.index].name.clone())
+        });
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("set_name", |_, this, name: String| {
+            this.dmi.borrow_mut().states[this.index].name = name;
+            Ok(())
+        });
+    }
+}
+
+/// Parses the Lua-facing resampling method name into an [`image`] filter.
+fn filter_type(method: &str) -> LuaResult<image::imageops::FilterType> {
+    Ok(match method {
+        "nearest" => image::imageops::FilterType::Nearest,
+        "triangle" => image::imageops::FilterType::Triangle,
+        "catmullrom" => image::imageops::FilterType::CatmullRom,
+        "gaussian" => image::imageops::FilterType::Gaussian,
+        "lanczos3" => image::imageops::FilterType::Lanczos3,
+        _ => return Err(LuaError::external(format!("unknown resize method: {method}"))),
+    })
+}
+
 /// Merges a PNG file with DMI metadata from an original DMI file and saves it as a new DMI file.
 /// This allows for editing DMI files as spritesheets while preserving metadata.
 /// 
@@ -49,72 +217,298 @@ fn merge_spritesheet(
     if !Path::new(&png_path).exists() {
         return Err(LuaError::external(format!("PNG file does not exist: {}", png_path)));
     }
-    
+
     if !Path::new(&dmi_path).exists() {
         return Err(LuaError::external(format!("DMI file does not exist: {}", dmi_path)));
     }
-    
+
     // Read both files into memory
-    let png_data = std::fs::read(&png_path)
-        .map_err(|e| LuaError::external(format!("Failed to read PNG file: {}", e)))?;
-    
-    let dmi_data = std::fs::read(&dmi_path)
-        .map_err(|e| LuaError::external(format!("Failed to read DMI file: {}", e)))?;
-    
-    // Find the zTXt chunk in the DMI file
-    let mut ztxt_pos = None;
-    let mut ztxt_length = 0;
-    
-    for i in 0..dmi_data.len() - 8 {
-        if &dmi_data[i+4..i+8] == b"zTXt" {
-            // Extract the length (big-endian u32)
-            let length = ((dmi_data[i] as u32) << 24) |
-                         ((dmi_data[i+1] as u32) << 16) |
-                         ((dmi_data[i+2] as u32) << 8) |
-                         (dmi_data[i+3] as u32);
-            
-            ztxt_pos = Some(i);
-            ztxt_length = length as usize + 12; // length + chunk type (4) + length field (4) + CRC (4)
-            break;
+    let png_data =
+        std::fs::read(&png_path).with_context(|| format!("reading edited PNG {png_path}"))?;
+
+    let dmi_data =
+        std::fs::read(&dmi_path).with_context(|| format!("reading DMI {dmi_path}"))?;
+
+    // Pull the `Description` metadata chunk out of the original DMI.
+    let (meta_type, meta_data, metadata) = extract_description(&dmi_data)
+        .with_context(|| format!("reading Description chunk from {dmi_path}"))?;
+
+    // Verify the edited sheet still tiles the DMI's state grid before writing.
+    let (sheet_width, sheet_height) = read_ihdr_dimensions(&png_data)
+        .with_context(|| format!("reading IHDR from {png_path}"))?;
+    let (cell_width, cell_height) = metadata_cell_size(&metadata)
+        .with_context(|| format!("parsing cell size from {dmi_path}"))?;
+
+    if cell_width == 0 || cell_height == 0 {
+        return Err(LuaError::external("DMI metadata reports a zero cell size"));
+    }
+
+    if sheet_width % cell_width != 0 || sheet_height % cell_height != 0 {
+        return Err(LuaError::external(format!(
+            "edited spritesheet ({sheet_width}x{sheet_height}) does not tile the \
+             DMI cell size ({cell_width}x{cell_height})"
+        )));
+    }
+
+    // Rebuild the PNG, inserting the metadata chunk ahead of the image data and
+    // recomputing its CRC over `type + data`.
+    let mut output_data = Vec::with_capacity(png_data.len() + meta_data.len() + 12);
+    output_data.extend_from_slice(&png_data[0..8]); // PNG signature
+
+    let mut inserted = false;
+    for chunk in PngChunks::new(&png_data[8..]) {
+        let chunk = chunk?;
+
+        // Drop any stale metadata already present in the edited PNG.
+        if is_description_chunk(chunk.kind, chunk.data) {
+            continue;
         }
+
+        // Metadata must precede the first IDAT to satisfy PNG ordering rules.
+        if chunk.kind == b"IDAT" && !inserted {
+            write_chunk(&mut output_data, meta_type, &meta_data);
+            inserted = true;
+        }
+
+        chunk.write_to(&mut output_data);
     }
-    
-    let ztxt_chunk = match ztxt_pos {
-        Some(pos) => &dmi_data[pos..pos + ztxt_length],
-        None => return Err(LuaError::external("Could not find DMI metadata in the file")),
-    };
-    
-    // Find the IDAT chunk in the PNG file
-    let mut idat_pos = None;
-    
-    for i in 0..png_data.len() - 8 {
-        if &png_data[i+4..i+8] == b"IDAT" {
-            idat_pos = Some(i);
-            break;
+
+    if !inserted {
+        return Err(LuaError::external("Could not find IDAT chunk in PNG file"));
+    }
+
+    std::fs::write(&output_path, output_data)
+        .map_err(|e| LuaError::external(format!("Failed to write output file: {}", e)))?;
+
+    Ok(true)
+}
+
+/// A single PNG chunk borrowed from the source buffer.
+struct PngChunk<'a> {
+    kind: &'a [u8; 4],
+    data: &'a [u8],
+    crc: u32,
+}
+
+impl PngChunk<'_> {
+    /// Appends this chunk verbatim (length, type, data, original CRC).
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&(self.data.len() as u32).to_be_bytes());
+        out.extend_from_slice(self.kind);
+        out.extend_from_slice(self.data);
+        out.extend_from_slice(&self.crc.to_be_bytes());
+    }
+}
+
+/// Iterator over `[length u32][type][data][crc u32]` records.
+struct PngChunks<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> PngChunks<'a> {
+    fn new(rest: &'a [u8]) -> Self {
+        Self { rest }
+    }
+}
+
+impl<'a> Iterator for PngChunks<'a> {
+    type Item = Result<PngChunk<'a>, ExternalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        if self.rest.len() < 12 {
+            return Some(Err("truncated PNG chunk header".into()));
         }
+
+        let length = u32::from_be_bytes([self.rest[0], self.rest[1], self.rest[2], self.rest[3]])
+            as usize;
+        let end = 8 + length;
+
+        if self.rest.len() < end + 4 {
+            return Some(Err("PNG chunk length exceeds file size".into()));
+        }
+
+        let kind: &[u8; 4] = self.rest[4..8].try_into().unwrap();
+        let data = &self.rest[8..end];
+        let crc = u32::from_be_bytes([
+            self.rest[end],
+            self.rest[end + 1],
+            self.rest[end + 2],
+            self.rest[end + 3],
+        ]);
+
+        self.rest = &self.rest[end + 4..];
+        Some(Ok(PngChunk { kind, data, crc }))
     }
-    
-    let idat_pos = match idat_pos {
-        Some(pos) => pos,
-        None => return Err(LuaError::external("Could not find IDAT chunk in PNG file")),
-    };
-    
-    // Merge the files
-    let mut output_data = Vec::with_capacity(png_data.len() + ztxt_length);
-    
-    // PNG header and chunks before IDAT
-    output_data.extend_from_slice(&png_data[0..idat_pos]);
-    
-    // Insert the zTXt chunk
-    output_data.extend_from_slice(ztxt_chunk);
-    
-    // Rest of the PNG file
-    output_data.extend_from_slice(&png_data[idat_pos..]);
-    
-    // Write the output file
-    match std::fs::write(&output_path, output_data) {
-        Ok(_) => Ok(true),
-        Err(e) => Err(LuaError::external(format!("Failed to write output file: {}", e))),
+}
+
+/// Appends a freshly built chunk, computing the CRC32 over `type + data`.
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+}
+
+/// True if `kind`/`data` is a text chunk carrying the DMI `Description` keyword.
+fn is_description_chunk(kind: &[u8; 4], data: &[u8]) -> bool {
+    matches!(kind, b"zTXt" | b"tEXt" | b"iTXt") && data.starts_with(b"Description\0")
+}
+
+/// Locates the DMI `Description` text chunk in `data`, returning its chunk
+/// type, raw chunk data, and the decoded (uncompressed) metadata text.
+fn extract_description(
+    data: &[u8],
+) -> Result<(&'static [u8; 4], Vec<u8>, String), ExternalError> {
+    if data.len() < 8 || &data[0..8] != b"\x89PNG\r\n\x1a\n" {
+        return Err("DMI file is not a valid PNG".into());
+    }
+
+    for chunk in PngChunks::new(&data[8..]) {
+        let chunk = chunk?;
+        if !is_description_chunk(chunk.kind, chunk.data) {
+            continue;
+        }
+
+        let keyword_len = b"Description\0".len();
+        let text = match chunk.kind {
+            // zTXt: keyword, null, compression method byte, zlib stream.
+            b"zTXt" => {
+                // +1 for the compression method byte following the keyword.
+                if chunk.data.len() <= keyword_len {
+                    return Err("truncated zTXt Description chunk".into());
+                }
+                let compressed = &chunk.data[keyword_len + 1..];
+                inflate(compressed)?
+            }
+            // tEXt: keyword, null, latin-1 text (uncompressed fallback).
+            b"tEXt" => String::from_utf8_lossy(&chunk.data[keyword_len..]).into_owned(),
+            // iTXt: keyword, null, compression flag, method, lang\0, translated\0, text.
+            b"iTXt" => extract_itxt_text(&chunk.data[keyword_len..])?,
+            _ => unreachable!(),
+        };
+
+        let kind: &'static [u8; 4] = match chunk.kind {
+            b"zTXt" => b"zTXt",
+            b"tEXt" => b"tEXt",
+            _ => b"iTXt",
+        };
+
+        return Ok((kind, chunk.data.to_vec(), text));
+    }
+
+    Err("Could not find DMI metadata in the file".into())
+}
+
+/// Decodes the payload of an `iTXt` chunk (after the keyword and its null).
+fn extract_itxt_text(rest: &[u8]) -> Result<String, ExternalError> {
+    // rest = [compression flag][method][language\0][translated keyword\0][text]
+    if rest.len() < 2 {
+        return Err("truncated iTXt Description chunk".into());
+    }
+
+    let compressed = rest.first().copied() == Some(1);
+    let mut cursor = 2; // skip compression flag + method
+
+    for _ in 0..2 {
+        match rest[cursor..].iter().position(|&b| b == 0) {
+            Some(pos) => cursor += pos + 1,
+            None => return Err("malformed iTXt chunk".into()),
+        }
+    }
+
+    let text = &rest[cursor..];
+    if compressed {
+        inflate(text)
+    } else {
+        Ok(String::from_utf8_lossy(text).into_owned())
+    }
+}
+
+/// Inflates a zlib stream into a UTF-8 string.
+fn inflate(compressed: &[u8]) -> Result<String, ExternalError> {
+    use std::io::Read;
+
+    let mut decoder = flate2::read::ZlibDecoder::new(compressed);
+    let mut out = String::new();
+    decoder
+        .read_to_string(&mut out)
+        .context("decompressing DMI metadata")?;
+
+    Ok(out)
+}
+
+/// Reads the image dimensions from the PNG's IHDR chunk.
+fn read_ihdr_dimensions(data: &[u8]) -> Result<(u32, u32), ExternalError> {
+    if data.len() < 8 || &data[0..8] != b"\x89PNG\r\n\x1a\n" {
+        return Err("edited file is not a valid PNG".into());
+    }
+
+    let ihdr = PngChunks::new(&data[8..])
+        .next()
+        .transpose()?
+        .filter(|chunk| chunk.kind == b"IHDR")
+        .ok_or_else(|| ExternalError::from("PNG is missing its IHDR chunk"))?;
+
+    if ihdr.data.len() < 8 {
+        return Err("malformed IHDR chunk".into());
+    }
+
+    let width = u32::from_be_bytes([ihdr.data[0], ihdr.data[1], ihdr.data[2], ihdr.data[3]]);
+    let height = u32::from_be_bytes([ihdr.data[4], ihdr.data[5], ihdr.data[6], ihdr.data[7]]);
+
+    Ok((width, height))
+}
+
+/// Parses the DMI cell `width`/`height` out of the decoded metadata text.
+fn metadata_cell_size(metadata: &str) -> Result<(u32, u32), ExternalError> {
+    let mut width = None;
+    let mut height = None;
+
+    for line in metadata.lines() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("width = ") {
+            width = value.trim().parse().ok();
+        } else if let Some(value) = line.strip_prefix("height = ") {
+            height = value.trim().parse().ok();
+        }
+    }
+
+    match (width, height) {
+        (Some(w), Some(h)) => Ok((w, h)),
+        _ => Err("DMI metadata is missing its cell dimensions".into()),
+    }
+}
+
+/// Minimal CRC32 (ISO-HDLC / PNG) over arbitrary byte runs.
+struct Crc32 {
+    value: u32,
+}
+
+impl Crc32 {
+    fn new() -> Self {
+        Self { value: 0xffff_ffff }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.value ^= byte as u32;
+            for _ in 0..8 {
+                let mask = (self.value & 1).wrapping_neg();
+                self.value = (self.value >> 1) ^ (0xedb8_8320 & mask);
+            }
+        }
+    }
+
+    fn finalize(self) -> u32 {
+        self.value ^ 0xffff_ffff
     }
 }
 fn new_file(
@@ -132,7 +526,10 @@ fn open_file(lua: &Lua, (filename, temp): (String, String)) -> LuaResult<LuaTabl
         Err("File does not exist".to_string()).into_lua_err()?
     }
 
-    let dmi = Dmi::open(filename)?.to_serialized(temp, false)?;
+    let dmi = Dmi::open(&filename)
+        .with_context(|| format!("opening DMI from {filename}"))?
+        .to_serialized(temp, false)
+        .with_context(|| format!("serializing DMI from {filename}"))?;
     let table: LuaTable = dmi.into_lua_table(lua)?;
 
     Ok(table)
@@ -140,8 +537,13 @@ fn open_file(lua: &Lua, (filename, temp): (String, String)) -> LuaResult<LuaTabl
 
 fn save_file(_: &Lua, (dmi, filename): (LuaTable, String)) -> LuaResult<LuaValue> {
     let dmi = SerializedDmi::from_lua_table(dmi)?;
-    let dmi = Dmi::from_serialized(dmi)?;
-    dmi.save(filename)?;
+    let dmi = Dmi::from_serialized(dmi)
+        .with_context(|| format!("reconstructing DMI for {filename}"))?;
+    dmi.save(filename.clone())
+        .with_context(|| format!("saving DMI to {filename}"))?;
+
+    // Let peers editing the same file know it changed on disk.
+    bus::publish("dmi-changed".to_string(), filename);
 
     Ok(LuaValue::Nil)
 }
@@ -166,6 +568,10 @@ fn copy_state(_: &Lua, (state, temp): (LuaTable, String)) -> LuaResult<LuaValue>
     let state = State::from_serialized(state, temp)?.into_clipboard()?;
     let state = serde_json::to_string(&state).map_err(ExternalError::Serde)?;
 
+    // Broadcast the serialized state so peers can paste it even when the system
+    // clipboard isn't shared or available between instances.
+    bus::publish("state-copied".to_string(), state.clone());
+
     let mut clipboard = arboard::Clipboard::new().map_err(ExternalError::Arboard)?;
     clipboard.set_text(state).map_err(ExternalError::Arboard)?;
 
@@ -186,6 +592,10 @@ fn paste_state(lua: &Lua, (width, height, temp): (u32, u32, String)) -> LuaResul
     Ok(table)
 }
 
+// Backward-compatible table wrappers. These mirror the `DmiHandle` userdata
+// methods — same `filter_type` parsing, same `Dmi::{resize,crop,expand}` calls —
+// but marshal through a serialized Lua table instead of holding a live handle.
+// New call sites should prefer `open_dmi`/`new_dmi` and the userdata methods.
 fn resize(
     _: &Lua,
     (dmi, width, height, method): (LuaTable, u32, u32, String),
@@ -193,18 +603,12 @@ fn resize(
     let dmi = SerializedDmi::from_lua_table(dmi)?;
 
     let temp = dmi.temp.clone();
-    let method = match method.as_str() {
-        "nearest" => image::imageops::FilterType::Nearest,
-        "triangle" => image::imageops::FilterType::Triangle,
-        "catmullrom" => image::imageops::FilterType::CatmullRom,
-        "gaussian" => image::imageops::FilterType::Gaussian,
-        "lanczos3" => image::imageops::FilterType::Lanczos3,
-        _ => unreachable!(),
-    };
+    let method = filter_type(&method)?;
 
-    let mut dmi = Dmi::from_serialized(dmi)?;
+    let mut dmi = Dmi::from_serialized(dmi).context("reconstructing DMI for resize")?;
     dmi.resize(width, height, method);
-    dmi.to_serialized(temp, true)?;
+    dmi.to_serialized(temp, true)
+        .with_context(|| format!("serializing resized DMI to {width}x{height}"))?;
 
     Ok(LuaValue::Nil)
 }
@@ -216,9 +620,9 @@ fn crop(
     let dmi = SerializedDmi::from_lua_table(dmi)?;
     let temp = dmi.temp.clone();
 
-    let mut dmi = Dmi::from_serialized(dmi)?;
+    let mut dmi = Dmi::from_serialized(dmi).context("reconstructing DMI for crop")?;
     dmi.crop(x, y, width, height);
-    dmi.to_serialized(temp, true)?;
+    dmi.to_serialized(temp, true).context("serializing cropped DMI")?;
 
     Ok(LuaValue::Nil)
 }
@@ -230,40 +634,273 @@ fn expand(
     let dmi = SerializedDmi::from_lua_table(dmi)?;
     let temp = dmi.temp.clone();
 
-    let mut dmi = Dmi::from_serialized(dmi)?;
+    let mut dmi = Dmi::from_serialized(dmi).context("reconstructing DMI for expand")?;
     dmi.expand(x, y, width, height);
-    dmi.to_serialized(temp, true)?;
+    dmi.to_serialized(temp, true).context("serializing expanded DMI")?;
 
     Ok(LuaValue::Nil)
 }
 
+/// Composites `bytes` (a raw RGBA buffer) over a solid `(r, g, b)` fill.
+///
+/// Pixel data crosses the FFI boundary as a contiguous Lua string rather than
+/// one boxed integer per byte: the input is read via [`mlua::String::as_bytes`]
+/// straight into the [`ImageBuffer`], and the result is returned with
+/// [`Lua::create_string`]. The Aseprite side reads the result as `image.bytes`.
 fn overlay_color(
-    _: &Lua,
-    (r, g, b, width, height, bytes): (u8, u8, u8, u32, u32, LuaMultiValue),
-) -> LuaResult<LuaMultiValue> {
+    lua: &Lua,
+    (r, g, b, width, height, bytes): (u8, u8, u8, u32, u32, mlua::String),
+) -> LuaResult<LuaValue> {
     use image::{imageops, EncodableLayout, ImageBuffer, Rgba};
 
-    let mut buf = Vec::new();
-    for byte in bytes {
-        if let LuaValue::Integer(byte) = byte {
-            buf.push(byte as u8);
-        }
-    }
+    let buf = bytes.as_bytes().to_vec();
 
     if let Some(top) = ImageBuffer::from_vec(width, height, buf) {
         let mut bottom = ImageBuffer::from_pixel(width, height, Rgba([r, g, b, 255]));
         imageops::overlay(&mut bottom, &top, 0, 0);
 
-        let bytes = bottom
-            .as_bytes()
-            .iter()
-            .map(|byte| LuaValue::Integer(*byte as i64))
-            .collect();
+        return Ok(LuaValue::String(lua.create_string(bottom.as_bytes())?));
+    }
+
+    Ok(LuaValue::Nil)
+}
+
+/// Publishes `payload` under `kind` to every other connected instance.
+fn publish(_: &Lua, (kind, payload): (String, String)) -> LuaResult<LuaValue> {
+    bus::publish(kind, payload);
+    Ok(LuaValue::Nil)
+}
+
+/// Subscribes this instance to `kind` so matching messages reach `poll`.
+fn subscribe(_: &Lua, kind: String) -> LuaResult<LuaValue> {
+    bus::subscribe(kind);
+    Ok(LuaValue::Nil)
+}
+
+/// Drains pending bus messages into an array of `{kind, payload}` tables.
+fn poll(lua: &Lua, _: ()) -> LuaResult<LuaTable> {
+    let messages = lua.create_table()?;
 
-        return Ok(LuaMultiValue::from_vec(bytes));
+    for message in bus::poll() {
+        let table = lua.create_table()?;
+        table.set("kind", message.kind)?;
+        table.set("payload", message.payload)?;
+        messages.push(table)?;
     }
 
-    Ok(LuaMultiValue::from_vec(vec![LuaValue::Nil]))
+    Ok(messages)
+}
+
+/// Progress shared between a transform worker thread and the Lua-facing handle.
+struct JobProgress {
+    total: usize,
+    done: AtomicUsize,
+    finished: AtomicBool,
+    error: std::sync::Mutex<Option<String>>,
+}
+
+/// Pollable handle to a background frame transform (resize, crop, or expand).
+/// Lua calls `job:poll()` each frame to read progress and learn when the
+/// transform has completed, then reads the output frames back with
+/// `job:frame(i)` / `job:result()`.
+#[derive(Clone)]
+struct FrameJob {
+    progress: Arc<JobProgress>,
+    /// The scratch file holding the transformed frames. Shared with the worker
+    /// and kept alive for the handle's lifetime so reads outlive the transform.
+    scratch: Arc<std::sync::Mutex<FrameScratch>>,
+    width: u32,
+    height: u32,
+}
+
+/// Spawns a memory-bounded frame transform: decodes `encoded` one at a time,
+/// applies `transform` to each, and parks the `width`x`height` result in a
+/// scratch file under `temp`. Returns a [`FrameJob`] the editor polls while it
+/// runs. Shared by `resize_async`, `crop_async`, and `expand_async`.
+fn spawn_frame_job<F>(
+    encoded: Vec<Vec<u8>>,
+    (src_width, src_height): (u32, u32),
+    (width, height): (u32, u32),
+    temp: String,
+    transform: F,
+) -> LuaResult<FrameJob>
+where
+    F: Fn(image::RgbaImage) -> image::RgbaImage + Send + 'static,
+{
+    let progress = Arc::new(JobProgress {
+        total: encoded.len(),
+        done: AtomicUsize::new(0),
+        finished: AtomicBool::new(false),
+        error: std::sync::Mutex::new(None),
+    });
+    let scratch = Arc::new(std::sync::Mutex::new(FrameScratch::create(
+        &temp, width, height,
+    )?));
+
+    let worker = progress.clone();
+    let worker_scratch = scratch.clone();
+    std::thread::spawn(move || {
+        if let Err(err) =
+            run_frame_job(encoded, (src_width, src_height), &worker_scratch, &worker, transform)
+        {
+            *worker.error.lock().unwrap() = Some(err.to_string());
+        }
+        worker.finished.store(true, AtomicOrdering::Release);
+    });
+
+    Ok(FrameJob {
+        progress,
+        scratch,
+        width,
+        height,
+    })
+}
+
+/// Worker body: decodes one frame at a time, applies `transform`, and parks the
+/// result in the scratch file at its computed offset.
+fn run_frame_job<F>(
+    encoded: Vec<Vec<u8>>,
+    (src_width, src_height): (u32, u32),
+    scratch: &std::sync::Mutex<FrameScratch>,
+    progress: &JobProgress,
+    transform: F,
+) -> Result<(), ExternalError>
+where
+    F: Fn(image::RgbaImage) -> image::RgbaImage,
+{
+    for decoded in scratch::decode_stream(encoded) {
+        let frame = decoded?;
+        let source = image::RgbaImage::from_raw(src_width, src_height, frame.rgba)
+            .ok_or_else(|| ExternalError::from("malformed scratch frame"))?;
+
+        let output = transform(source);
+        scratch
+            .lock()
+            .unwrap()
+            .write_frame(frame.index, output.as_raw())?;
+        progress.done.fetch_add(1, AtomicOrdering::Relaxed);
+    }
+
+    Ok(())
+}
+
+/// Collects the raw PNG byte strings Lua passes into owned buffers.
+fn collect_frames(frames: &[mlua::String]) -> Vec<Vec<u8>> {
+    frames.iter().map(|f| f.as_bytes().to_vec()).collect()
+}
+
+/// Starts a background, memory-bounded resize to `width`x`height`.
+fn resize_async(
+    _: &Lua,
+    (frames, src_width, src_height, width, height, method, temp): (
+        Vec<mlua::String>,
+        u32,
+        u32,
+        u32,
+        u32,
+        String,
+        String,
+    ),
+) -> LuaResult<FrameJob> {
+    let method = filter_type(&method)?;
+    let encoded = collect_frames(&frames);
+
+    spawn_frame_job(encoded, (src_width, src_height), (width, height), temp, move |src| {
+        image::imageops::resize(&src, width, height, method)
+    })
+}
+
+/// Starts a background, memory-bounded crop of each frame to the `width`x`height`
+/// rectangle anchored at `(x, y)`.
+fn crop_async(
+    _: &Lua,
+    (frames, src_width, src_height, x, y, width, height, temp): (
+        Vec<mlua::String>,
+        u32,
+        u32,
+        u32,
+        u32,
+        u32,
+        u32,
+        String,
+    ),
+) -> LuaResult<FrameJob> {
+    let encoded = collect_frames(&frames);
+
+    spawn_frame_job(encoded, (src_width, src_height), (width, height), temp, move |src| {
+        image::imageops::crop_imm(&src, x, y, width, height).to_image()
+    })
+}
+
+/// Starts a background, memory-bounded expand: each frame is composited onto a
+/// transparent `width`x`height` canvas at offset `(x, y)`.
+fn expand_async(
+    _: &Lua,
+    (frames, src_width, src_height, x, y, width, height, temp): (
+        Vec<mlua::String>,
+        u32,
+        u32,
+        u32,
+        u32,
+        u32,
+        u32,
+        String,
+    ),
+) -> LuaResult<FrameJob> {
+    let encoded = collect_frames(&frames);
+
+    spawn_frame_job(encoded, (src_width, src_height), (width, height), temp, move |src| {
+        let mut canvas = image::RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 0]));
+        image::imageops::overlay(&mut canvas, &src, x as i64, y as i64);
+        canvas
+    })
+}
+
+impl LuaUserData for FrameJob {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("poll", |lua, this, (): ()| {
+            let table = lua.create_table()?;
+            let done = this.progress.done.load(AtomicOrdering::Relaxed);
+            table.set("done", done)?;
+            table.set("total", this.progress.total)?;
+            table.set(
+                "progress",
+                if this.progress.total == 0 {
+                    1.0
+                } else {
+                    done as f64 / this.progress.total as f64
+                },
+            )?;
+            table.set(
+                "finished",
+                this.progress.finished.load(AtomicOrdering::Acquire),
+            )?;
+            if let Some(error) = this.progress.error.lock().unwrap().clone() {
+                table.set("error", error)?;
+            }
+            Ok(table)
+        });
+
+        // Reads a single transformed frame back as a raw RGBA byte string.
+        methods.add_method("frame", |lua, this, index: usize| {
+            let rgba = this.scratch.lock().unwrap().read_frame(index)?;
+            Ok(LuaValue::String(lua.create_string(&rgba)?))
+        });
+
+        // Reads every transformed frame back as an array of RGBA byte strings.
+        methods.add_method("result", |lua, this, (): ()| {
+            let frames = lua.create_table()?;
+            let mut scratch = this.scratch.lock().unwrap();
+            for index in 0..this.progress.total {
+                let rgba = scratch.read_frame(index)?;
+                frames.push(lua.create_string(&rgba)?)?;
+            }
+            Ok(frames)
+        });
+
+        methods.add_method("width", |_, this, (): ()| Ok(this.width));
+        methods.add_method("height", |_, this, (): ()| Ok(this.height));
+    }
 }
 
 fn remove_dir(_: &Lua, (path, soft): (String, bool)) -> LuaResult<LuaValue> {
@@ -0,0 +1,122 @@
+//! Scratch-file frame store used to bound memory when transforming large
+//! spritesheets.
+//!
+//! Decoding every frame of every state up front keeps all RGBA buffers
+//! resident at once, which is painful for DMIs with dozens of states and large
+//! cell sizes. Instead a worker thread decodes frames one at a time, and each
+//! transformed, uncompressed RGBA frame is written to a scratch temp file at a
+//! fixed offset (`frame_index * width * height * 4`, using the output size).
+//! Only a small bounded window of decoded frames is in flight via a
+//! [`sync_channel`]; results are read back by seeking to their computed offset.
+//! The scratch file holds one job's output only: source frames are not kept,
+//! so every job decodes its source PNGs again.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, Receiver};
+
+use crate::errors::ExternalError;
+
+/// How many decoded frames may wait in the channel before the decoder blocks.
+const WINDOW: usize = 4;
+
+/// A single decoded RGBA frame paired with its position in the sheet.
+pub struct DecodedFrame {
+    pub index: usize,
+    pub rgba: Vec<u8>,
+}
+
+/// Fixed-layout scratch file holding uncompressed RGBA frames back to back.
+pub struct FrameScratch {
+    file: File,
+    path: PathBuf,
+    frame_len: u64,
+}
+
+impl FrameScratch {
+    /// Creates an empty scratch file under `temp` sized for `width * height`
+    /// RGBA frames. The file name is unique per job so concurrent transforms
+    /// sharing a temp directory never clobber each other's frames.
+    pub fn create(temp: impl AsRef<Path>, width: u32, height: u32) -> Result<Self, ExternalError> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = temp
+            .as_ref()
+            .join(format!("dmi-frames-{}-{}.scratch", std::process::id(), unique));
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .map_err(ExternalError::Io)?;
+
+        Ok(Self {
+            file,
+            path,
+            frame_len: width as u64 * height as u64 * 4,
+        })
+    }
+
+    fn offset(&self, index: usize) -> u64 {
+        index as u64 * self.frame_len
+    }
+
+    /// Writes a frame's RGBA bytes at its computed offset.
+    pub fn write_frame(&mut self, index: usize, rgba: &[u8]) -> Result<(), ExternalError> {
+        self.file
+            .seek(SeekFrom::Start(self.offset(index)))
+            .map_err(ExternalError::Io)?;
+        self.file.write_all(rgba).map_err(ExternalError::Io)?;
+        Ok(())
+    }
+
+    /// Reads a frame's RGBA bytes back by seeking to its offset.
+    pub fn read_frame(&mut self, index: usize) -> Result<Vec<u8>, ExternalError> {
+        let mut buf = vec![0u8; self.frame_len as usize];
+        self.file
+            .seek(SeekFrom::Start(self.offset(index)))
+            .map_err(ExternalError::Io)?;
+        self.file.read_exact(&mut buf).map_err(ExternalError::Io)?;
+        Ok(buf)
+    }
+}
+
+impl Drop for FrameScratch {
+    fn drop(&mut self) {
+        // Best effort: the temp directory is cleaned up regardless, but remove
+        // the scratch eagerly so it doesn't outlive the transform.
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Spawns a worker that decodes `frames` one at a time, streaming each decoded
+/// frame through a bounded channel so at most [`WINDOW`] frames are queued
+/// (plus the one being decoded and the one the receiver is processing).
+///
+/// Each item in `frames` is the encoded PNG bytes of one frame; decoding
+/// happens off the calling thread to keep the editor UI responsive.
+pub fn decode_stream(frames: Vec<Vec<u8>>) -> Receiver<Result<DecodedFrame, ExternalError>> {
+    let (tx, rx) = sync_channel(WINDOW);
+
+    std::thread::spawn(move || {
+        for (index, encoded) in frames.into_iter().enumerate() {
+            let decoded = image::load_from_memory(&encoded)
+                .map(|img| DecodedFrame {
+                    index,
+                    rgba: img.to_rgba8().into_raw(),
+                })
+                .map_err(ExternalError::Image);
+
+            // Receiver gone (job cancelled): stop decoding early.
+            if tx.send(decoded).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}